@@ -16,6 +16,8 @@
  *   - 50 s of total wire silence after handshake → `ws.close()` called.
  *   - A frame at t=30 s defers the force-close: nothing at t=50 s, then
  *     force-close at t=80 s once 45 s have elapsed since the last frame.
+ *   - Back-to-back header-extension frames split across messages are
+ *     reassembled at their true boundaries.
  *
  * Mirrors [D02] / `tugcast/src/router.rs:48`.
  */
@@ -34,7 +36,7 @@ if (typeof (globalThis as { window?: unknown }).window === "undefined") {
 }
 
 import { TugConnection } from "@/connection";
-import { encodeFrame, FeedId, FrameFlags } from "@/protocol";
+import { encodeFrame, FeedId, FrameFlags, HEADER_SIZE } from "@/protocol";
 
 // ---------------------------------------------------------------------------
 // FakeWebSocket — a passive stand-in for the browser's WebSocket.
@@ -327,3 +329,79 @@ describe("TugConnection — lastPayload cleared on close (Step 3)", () => {
     expect(lateDeliveries.length).toBe(0);
   });
 });
+
+/**
+ * Hand-encode a frame carrying a metadata extension and, when `timestamp`
+ * is given, a timestamp extension after it. `encodeFrame` only writes the
+ * plain header, and the layout is exactly what these tests are about, so
+ * spell it out byte for byte.
+ */
+function extensionFrame(
+  feedId: number,
+  meta: number[],
+  payload: string,
+  timestamp?: bigint,
+): Uint8Array {
+  const body = new TextEncoder().encode(payload);
+  const extensions = 2 + meta.length + (timestamp === undefined ? 0 : 8);
+  const bytes = new Uint8Array(HEADER_SIZE + extensions + body.length);
+  const view = new DataView(bytes.buffer);
+  view.setUint8(0, feedId);
+  view.setUint8(
+    1,
+    timestamp === undefined ? FrameFlags.META : FrameFlags.META | FrameFlags.TIMESTAMP,
+  );
+  view.setUint32(2, body.length, false);
+  view.setUint16(HEADER_SIZE, meta.length, false);
+  bytes.set(meta, HEADER_SIZE + 2);
+  if (timestamp !== undefined) {
+    view.setBigUint64(HEADER_SIZE + 2 + meta.length, timestamp, false);
+  }
+  bytes.set(body, HEADER_SIZE + extensions);
+  return bytes;
+}
+
+describe("TugConnection — header-extension reassembly", () => {
+  beforeEach(() => {
+    installFakes();
+  });
+
+  afterEach(() => {
+    uninstallFakes();
+  });
+
+  it("cuts back-to-back extension frames at their true end across chunks", () => {
+    const conn = new TugConnection("ws://test.invalid/");
+    const ws = completeHandshake(conn);
+
+    const deliveries: string[] = [];
+    conn.onFrame(FeedId.SESSION_STATE, (payload) => {
+      deliveries.push(new TextDecoder().decode(payload));
+    });
+
+    // Two extension frames and a plain one behind them, as one byte
+    // stream. If the reader cut frames at `HEADER_SIZE + length`, the
+    // first frame would lose its payload tail and every later header
+    // would be read from the wrong offset. The second frame also
+    // carries a timestamp, whose 8 bytes the length field omits too.
+    const first = extensionFrame(FeedId.SESSION_STATE, [1, 2, 0xaa, 0xbb], "first");
+    const second = extensionFrame(FeedId.SESSION_STATE, [7, 1, 0xcc], "second", 7n);
+    const third = new Uint8Array(sessionStateFrame({ state: "plain" }));
+    const stream = new Uint8Array(first.length + second.length + third.length);
+    stream.set(first, 0);
+    stream.set(second, first.length);
+    stream.set(third, first.length + second.length);
+
+    // Five-byte chunks split headers, region-length prefixes, metadata
+    // entries, timestamps and payloads alike.
+    for (let at = 0; at < stream.length; at += 5) {
+      ws.fireMessage(stream.slice(at, at + 5).buffer);
+    }
+
+    expect(deliveries).toEqual([
+      "first",
+      "second",
+      JSON.stringify({ state: "plain" }),
+    ]);
+  });
+});
@@ -4,6 +4,7 @@ import {
   CONTROL_ACTION_RESET_SESSION,
   CONTROL_ACTION_SPAWN_SESSION,
   FEED_ID_SESSION_STATE,
+  FRAME_CAPABILITIES,
   FeedId,
   FrameFlags,
   HEADER_SIZE,
@@ -162,6 +163,55 @@ describe("wire format v1", () => {
     expect(decoded.feedId).toBe(0x99 as typeof decoded.feedId);
     expect(new TextDecoder().decode(decoded.payload)).toBe("opaque");
   });
+  test("decode reads the metadata extension", () => {
+    // Golden bytes from tugcast-core `test_meta_golden_bytes`.
+    const bytes = new Uint8Array([
+      0x30, 0x08, 0x00, 0x00, 0x00, 0x02, // header, META flag, length 2
+      0x00, 0x04, // region length 4
+      0x01, 0x02, 0x6a, 0x73, // entry: type 1, length 2, "js"
+      0x7b, 0x7d, // payload
+    ]);
+    const decoded = decodeFrame(bytes.buffer);
+    expect(decoded.feedId).toBe(FeedId.STATS);
+    expect(decoded.meta).toEqual([[0x01, new TextEncoder().encode("js")]]);
+    expect(new TextDecoder().decode(decoded.payload)).toBe("{}");
+  });
+
+  test("decode rejects an overrunning metadata entry", () => {
+    const bytes = new Uint8Array([0x30, 0x08, 0, 0, 0, 0, 0x00, 0x03, 0x01, 0x05, 0x78]);
+    expect(() => decodeFrame(bytes.buffer)).toThrow("invalid frame metadata");
+  });
+
+  test("frames without extensions carry no meta", () => {
+    const encoded = encodeFrame({
+      feedId: FeedId.STATS as (typeof FeedId)[keyof typeof FeedId],
+      flags: FrameFlags.DATA,
+      payload: new TextEncoder().encode("{}"),
+    });
+    expect(decodeFrame(encoded).meta).toBeUndefined();
+    expect(decodeFrame(encoded).timestamp).toBeUndefined();
+  });
+
+  test("decode reads the timestamp extension after metadata", () => {
+    // Golden bytes from tugcast-core `test_timestamp_follows_meta_region`.
+    const bytes = new Uint8Array([
+      0x30, 0x19, 0x00, 0x00, 0x00, 0x02, // header, CONTROL|META|TIMESTAMP
+      0x00, 0x04, 0x01, 0x02, 0x6a, 0x73, // metadata region
+      0, 0, 0, 0, 0, 0, 0, 7, // timestamp
+      0x7b, 0x7d, // payload
+    ]);
+    const decoded = decodeFrame(bytes.buffer);
+    expect(isControlFrame(decoded.flags)).toBe(true);
+    expect(decoded.timestamp).toBe(7n);
+    expect(decoded.meta).toEqual([[0x01, new TextEncoder().encode("js")]]);
+    expect(new TextDecoder().decode(decoded.payload)).toBe("{}");
+  });
+
+  test("handshake capabilities name only decodable extensions", () => {
+    expect(FRAME_CAPABILITIES).toContain("frame_meta");
+    expect(FRAME_CAPABILITIES).toContain("frame_timestamp");
+    expect(FRAME_CAPABILITIES).not.toContain("partial_frames");
+  });
 });
 
 describe("session protocol constants", () => {
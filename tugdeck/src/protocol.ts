@@ -9,8 +9,23 @@
  * - FeedId: open u8 namespace — known feeds have named constants,
  *   unknown values pass through without error (opaque routing).
  * - Flags: bit 0 = frame kind (0 = data, 1 = control/meta).
- *   Bits 1–7 are reserved; receivers ignore unknown flags.
+ *   Bit 1 = partial (one piece of a streamed snapshot), bit 2 = final piece.
+ *   Partial pieces are opt-in via the `partial_frames` handshake capability;
+ *   tugdeck does not declare it, so every snapshot it receives is whole.
+ *   Bit 3 = metadata extension present, bit 4 = timestamp extension present
+ *   (see below). Bit 5 = payload is UTF-8 text (0 = binary); a plain flag,
+ *   no layout change. Bits 6–7 are reserved; receivers ignore unknown flags.
  * - Length: big-endian u32, max MAX_PAYLOAD_SIZE.
+ *
+ * Header extensions insert a region between the header and the payload; the
+ * length field still counts only the payload. They are opt-in: the server
+ * only sends an extension that the client's handshake `capabilities` list
+ * (see FRAME_CAPABILITIES).
+ *
+ * - Metadata (bit 3, `frame_meta`): `[2 bytes region length (BE u16)][entries]`,
+ *   each entry `[1 byte type][1 byte value length][value]`.
+ * - Timestamp (bit 4, `frame_timestamp`): `[8 bytes send time (BE u64)]`,
+ *   after the metadata region when both are present.
  */
 
 import type { InboundMessage, ReplayWindow } from "@tugproto/inbound";
@@ -254,6 +269,16 @@ export const FrameFlags = {
   DATA: 0x00,
   /** Control/meta frame about this feed */
   CONTROL: 0x01,
+  /** One piece of a streamed snapshot (opt-in; see module doc) */
+  PARTIAL: 0x02,
+  /** Last piece of a streamed snapshot; only set together with PARTIAL */
+  FINAL: 0x04,
+  /** A metadata extension region follows the header */
+  META: 0x08,
+  /** An 8-byte send timestamp follows the header (after any metadata) */
+  TIMESTAMP: 0x10,
+  /** Payload is UTF-8 text (usually JSON); clear means raw binary */
+  TEXT: 0x20,
 } as const;
 
 export type FrameFlagsValue = (typeof FrameFlags)[keyof typeof FrameFlags];
@@ -264,6 +289,12 @@ const KIND_BIT = 0x01;
 /** Frame header size in bytes (1 FeedId + 1 flags + 4 length) */
 export const HEADER_SIZE = 6;
 
+/**
+ * Opt-in frame features tugdeck declares in its handshake hello. Each name
+ * must be one `decodeFrame` handles; `partial_frames` is deliberately absent.
+ */
+export const FRAME_CAPABILITIES: readonly string[] = ["frame_meta", "frame_timestamp"];
+
 /** Maximum payload size in bytes (16 MB) */
 export const MAX_PAYLOAD_SIZE = 16 * 1024 * 1024;
 
@@ -272,6 +303,10 @@ export interface Frame {
   feedId: FeedIdValue;
   flags: number;
   payload: Uint8Array;
+  /** Metadata extension entries as `[type, value]`, in wire order */
+  meta?: Array<[number, Uint8Array]>;
+  /** Sender's send timestamp (clock agreed per feed) */
+  timestamp?: bigint;
 }
 
 /**
@@ -292,6 +327,30 @@ export function encodeFrame(frame: Frame): ArrayBuffer {
   return buffer;
 }
 
+/**
+ * Total wire size of the frame whose header starts `bytes`: header, header
+ * extensions and payload. `bytes` must hold at least `HEADER_SIZE` bytes.
+ *
+ * Until the metadata region's length prefix has arrived the result is a lower
+ * bound that `bytes` is already shorter than, so a stream reader can always
+ * wait until `bytes.length >= frameSize(bytes)` and then cut the frame there.
+ */
+export function frameSize(bytes: Uint8Array): number {
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const flags = view.getUint8(1);
+  let size = HEADER_SIZE + view.getUint32(2, false);
+  if ((flags & FrameFlags.META) !== 0) {
+    size += 2;
+    if (bytes.length >= HEADER_SIZE + 2) {
+      size += view.getUint16(HEADER_SIZE, false);
+    }
+  }
+  if ((flags & FrameFlags.TIMESTAMP) !== 0) {
+    size += 8;
+  }
+  return size;
+}
+
 /**
  * Decode a frame from wire format bytes
  *
@@ -314,16 +373,47 @@ export function decodeFrame(data: ArrayBuffer): Frame {
     throw new Error(`payload too large: ${length} bytes`);
   }
 
-  if (data.byteLength < HEADER_SIZE + length) {
+  const size = frameSize(new Uint8Array(data));
+  if (data.byteLength < size) {
     throw new Error(
-      `incomplete frame: need ${HEADER_SIZE + length} bytes, have ${data.byteLength}`
+      `incomplete frame: need ${size} bytes, have ${data.byteLength}`
     );
   }
 
+  let offset = HEADER_SIZE;
+  let meta: Array<[number, Uint8Array]> | undefined;
+  if ((flags & FrameFlags.META) !== 0) {
+    const regionEnd = offset + 2 + view.getUint16(offset, false);
+    meta = [];
+    let pos = offset + 2;
+    while (pos < regionEnd) {
+      if (pos + 2 > regionEnd) {
+        throw new Error("invalid frame metadata: truncated entry header");
+      }
+      const kind = view.getUint8(pos);
+      const valueLength = view.getUint8(pos + 1);
+      if (pos + 2 + valueLength > regionEnd) {
+        throw new Error(`invalid frame metadata: entry ${kind} overruns the region`);
+      }
+      meta.push([kind, new Uint8Array(data, pos + 2, valueLength)]);
+      pos += 2 + valueLength;
+    }
+    offset = regionEnd;
+  }
+
+  let timestamp: bigint | undefined;
+  if ((flags & FrameFlags.TIMESTAMP) !== 0) {
+    timestamp = view.getBigUint64(offset, false);
+    offset += 8;
+  }
+
   // View into original buffer, no copy
-  const payload = new Uint8Array(data, HEADER_SIZE, length);
+  const payload = new Uint8Array(data, offset, length);
 
-  return { feedId, flags, payload };
+  const frame: Frame = { feedId, flags, payload };
+  if (meta !== undefined) frame.meta = meta;
+  if (timestamp !== undefined) frame.timestamp = timestamp;
+  return frame;
 }
 
 /** Returns true if the flags indicate a control/meta frame */
@@ -0,0 +1,123 @@
+//! FeedId-keyed frame dispatch.
+//!
+//! Consumers of decoded frames otherwise hand-roll a `match frame.feed_id`
+//! to reach the right handler. [`FrameRouter`] replaces that with a table:
+//! handlers are registered per [`FeedId`], and anything unregistered goes to
+//! a fallback, mirroring the open-namespace rule that unknown feeds are
+//! routed rather than rejected.
+
+use std::collections::HashMap;
+
+use crate::protocol::{FeedId, Frame};
+
+/// A frame handler: consumes the frame, produces the router's output type.
+pub type FrameHandler<T> = Box<dyn FnMut(Frame) -> T + Send>;
+
+/// Dispatches frames to per-feed handlers, with a fallback for the rest.
+///
+/// `T` is what every handler returns — `()` for fire-and-forget handlers,
+/// or e.g. a `Result` when the caller wants to observe handler failures.
+pub struct FrameRouter<T = ()> {
+    handlers: HashMap<FeedId, FrameHandler<T>>,
+    fallback: FrameHandler<T>,
+}
+
+impl<T> FrameRouter<T> {
+    /// Create a router that sends every frame to `fallback` until handlers
+    /// are registered.
+    pub fn new(fallback: impl FnMut(Frame) -> T + Send + 'static) -> Self {
+        Self {
+            handlers: HashMap::new(),
+            fallback: Box::new(fallback),
+        }
+    }
+
+    /// Register `handler` for `feed_id`, replacing any earlier registration.
+    pub fn on(mut self, feed_id: FeedId, handler: impl FnMut(Frame) -> T + Send + 'static) -> Self {
+        self.handlers.insert(feed_id, Box::new(handler));
+        self
+    }
+
+    /// Returns `true` if a handler (not the fallback) serves `feed_id`.
+    pub fn handles(&self, feed_id: FeedId) -> bool {
+        self.handlers.contains_key(&feed_id)
+    }
+
+    /// Route `frame` to its feed's handler, or to the fallback.
+    pub fn dispatch(&mut self, frame: Frame) -> T {
+        match self.handlers.get_mut(&frame.feed_id) {
+            Some(handler) => handler(frame),
+            None => (self.fallback)(frame),
+        }
+    }
+}
+
+// Manual Debug (the handlers are opaque closures).
+impl<T> std::fmt::Debug for FrameRouter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut feeds: Vec<FeedId> = self.handlers.keys().copied().collect();
+        feeds.sort_by_key(|id| id.as_byte());
+        f.debug_struct("FrameRouter")
+            .field("feeds", &feeds)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_dispatch_reaches_registered_handlers() {
+        let seen: Arc<Mutex<Vec<String>>> = Arc::default();
+        let (a, b, c) = (seen.clone(), seen.clone(), seen.clone());
+        let mut router = FrameRouter::new(move |f: Frame| {
+            c.lock().unwrap().push(format!("fallback:{}", f.feed_id));
+        })
+        .on(FeedId::CODE_OUTPUT, move |f| {
+            a.lock().unwrap().push(format!("code:{}", f.payload.len()));
+        })
+        .on(FeedId::FILETREE, move |_| {
+            b.lock().unwrap().push("filetree".to_string());
+        });
+
+        router.dispatch(Frame::new(FeedId::CODE_OUTPUT, b"abc".to_vec()));
+        router.dispatch(Frame::new(FeedId::FILETREE, vec![]));
+        router.dispatch(Frame::new(FeedId(0x99), vec![]));
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec!["code:3", "filetree", "fallback:0x99"]
+        );
+    }
+
+    #[test]
+    fn test_dispatch_returns_handler_output() {
+        let mut router =
+            FrameRouter::new(|_| Err("unrouted")).on(FeedId::STATS, |f: Frame| Ok(f.payload.len()));
+
+        assert_eq!(
+            router.dispatch(Frame::new(FeedId::STATS, b"{}".to_vec())),
+            Ok(2)
+        );
+        assert_eq!(
+            router.dispatch(Frame::new(FeedId::HEARTBEAT, vec![])),
+            Err("unrouted")
+        );
+        assert!(router.handles(FeedId::STATS));
+        assert!(!router.handles(FeedId::HEARTBEAT));
+    }
+
+    #[test]
+    fn test_later_registration_replaces_earlier() {
+        let mut router = FrameRouter::new(|_| 0)
+            .on(FeedId::PULSE, |_| 1)
+            .on(FeedId::PULSE, |_| 2);
+        assert_eq!(router.dispatch(Frame::new(FeedId::PULSE, vec![])), 2);
+        assert_eq!(
+            format!("{router:?}"),
+            "FrameRouter { feeds: [FeedId::Pulse(0x80)] }"
+        );
+    }
+}
@@ -7,31 +7,48 @@
 //! ## Modules
 //!
 //! - [`protocol`] - Binary frame protocol and FeedId definitions
+//! - [`dispatch`] - FeedId-keyed frame routing table
 //! - [`feed`] - Feed traits for stream and snapshot feeds
 //! - [`lag`] - Lag-recovery policy and replay buffer for stream feeds
+//! - [`log_tail`] - Log-file tailing stream feed
+//! - [`proc_stats`] - Process CPU/memory snapshot feed
 //! - [`types`] - Data structures for snapshot feeds (FsEvent, GitStatus)
 
+pub mod dispatch;
 pub mod feed;
 pub mod lag;
+pub mod log_tail;
+pub mod proc_stats;
 pub mod protocol;
 pub mod types;
 
+pub use dispatch::{FrameHandler, FrameRouter};
 pub use feed::{DEFAULT_BROADCAST_CAPACITY, SnapshotFeed, StreamFeed, spawn_snapshot_feed};
 pub use lag::{LagPolicy, ReplayBuffer};
+pub use log_tail::{FileLogReader, LogTailFeed, LogTailReader};
+pub use proc_stats::{ProcReading, ProcStatsFeed, ProcStatsReader};
 pub use protocol::{
     // Handshake constants
+    CAP_FRAME_META,
+    CAP_FRAME_TIMESTAMP,
+    CAP_PARTIAL_FRAMES,
     CLOSE_BAD_HANDSHAKE,
     CLOSE_HANDSHAKE_TIMEOUT,
     CLOSE_VERSION_MISMATCH,
+    FeedError,
     FeedId,
     Frame,
+    FrameBuilder,
     FrameFlags,
     HANDSHAKE_TIMEOUT,
     HEADER_SIZE,
     MAX_PAYLOAD_SIZE,
     PROTOCOL_NAME,
     PROTOCOL_VERSION,
+    PayloadKind,
+    PeerCapabilities,
     ProtocolError,
+    SnapshotAssembler,
     TugSessionId,
 };
 pub use types::{FileStatus, FileTreeSnapshot, FsEvent, GitStatus, ScoredResult, StatSnapshot};
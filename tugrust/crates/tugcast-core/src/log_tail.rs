@@ -0,0 +1,419 @@
+//! Log-file tailing stream feed.
+//!
+//! [`LogTailFeed`] polls a file on a fixed interval and broadcasts every
+//! newly appended line as one frame. File access goes through
+//! [`LogTailReader`], so the real filesystem probe ([`FileLogReader`]) stays
+//! swappable and tests can script appends and truncations.
+//!
+//! Truncation is detected by the file shrinking below the read offset, and
+//! rotation by the file's identity (device and inode) changing; either way
+//! the feed starts over from the beginning of the file.
+//!
+//! Reads are capped at [`MAX_LOG_TAIL_READ`] bytes and run on a blocking
+//! thread, so a burst of appended output neither stalls the executor nor
+//! lands in memory all at once; the feed catches up chunk by chunk.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+use crate::feed::StreamFeed;
+use crate::protocol::{FeedId, Frame, MAX_PAYLOAD_SIZE};
+
+/// Default polling interval for [`LogTailFeed`].
+pub const DEFAULT_LOG_TAIL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Most bytes [`LogTailFeed`] asks its reader for in one read.
+pub const MAX_LOG_TAIL_READ: usize = 1024 * 1024;
+
+/// Source of file contents for a [`LogTailFeed`].
+///
+/// All methods may perform blocking I/O; the feed calls them from
+/// `spawn_blocking`.
+pub trait LogTailReader: Send + Sync {
+    /// Current size of the file in bytes. Returns `None` if the file cannot
+    /// be read (missing, permission denied, …).
+    fn size(&mut self) -> Option<u64>;
+
+    /// Identity of the file currently behind the reader, as
+    /// `(device, inode)`. A different identity means the file was replaced,
+    /// even when the replacement is already larger than the read offset.
+    /// Returns `None` if the file cannot be read.
+    fn identity(&mut self) -> Option<(u64, u64)>;
+
+    /// Read at most `max` bytes starting at `offset`.
+    fn read_from(&mut self, offset: u64, max: usize) -> Option<Vec<u8>>;
+}
+
+/// [`LogTailReader`] over a file on disk.
+pub struct FileLogReader {
+    path: PathBuf,
+}
+
+impl FileLogReader {
+    /// Create a reader for `path`. The file need not exist yet.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl LogTailReader for FileLogReader {
+    fn size(&mut self) -> Option<u64> {
+        std::fs::metadata(&self.path).ok().map(|m| m.len())
+    }
+
+    fn identity(&mut self) -> Option<(u64, u64)> {
+        std::fs::metadata(&self.path)
+            .ok()
+            .map(|m| (m.dev(), m.ino()))
+    }
+
+    fn read_from(&mut self, offset: u64, max: usize) -> Option<Vec<u8>> {
+        let mut file = File::open(&self.path).ok()?;
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        let mut bytes = Vec::new();
+        file.take(max as u64).read_to_end(&mut bytes).ok()?;
+        Some(bytes)
+    }
+}
+
+/// Stream feed broadcasting lines appended to a file.
+///
+/// Each frame's payload is one line without its trailing `\n` (or `\r\n`).
+/// A line longer than [`MAX_PAYLOAD_SIZE`] is sent as several frames of at
+/// most that size. A trailing fragment without a newline is held back until
+/// the line is completed, except that whole [`MAX_PAYLOAD_SIZE`] pieces of
+/// it are flushed as soon as they exceed the limit.
+pub struct LogTailFeed<R> {
+    feed_id: FeedId,
+    reader: R,
+    interval: Duration,
+    /// Identity of the file `offset` refers to.
+    identity: Option<(u64, u64)>,
+    offset: u64,
+    pending: Vec<u8>,
+    /// The last poll stopped at the read cap short of the end of the file.
+    behind: bool,
+}
+
+impl<R: LogTailReader> LogTailFeed<R> {
+    /// Create a feed tailing through `reader`, publishing on `feed_id`.
+    ///
+    /// Tailing starts at the current end of the file: content already
+    /// present is not replayed.
+    pub fn new(feed_id: FeedId, mut reader: R) -> Self {
+        let offset = reader.size().unwrap_or(0);
+        let identity = reader.identity();
+        Self {
+            feed_id,
+            reader,
+            interval: DEFAULT_LOG_TAIL_INTERVAL,
+            identity,
+            offset,
+            pending: Vec::new(),
+            behind: false,
+        }
+    }
+
+    /// Override the polling interval.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Read what was appended since the last poll, up to
+    /// [`MAX_LOG_TAIL_READ`] bytes, and return the completed lines split into
+    /// frame-sized pieces.
+    ///
+    /// A file smaller than the read offset was truncated, and a file with a
+    /// new identity was rotated: either way the offset and any held-back
+    /// fragment are discarded and reading restarts at the beginning of the
+    /// file.
+    pub fn poll(&mut self) -> Vec<Vec<u8>> {
+        self.behind = false;
+        let Some(size) = self.reader.size() else {
+            return Vec::new();
+        };
+        let identity = self.reader.identity();
+        let rotated = identity.is_some() && identity != self.identity;
+        if rotated {
+            self.identity = identity;
+        }
+        if rotated || size < self.offset {
+            self.offset = 0;
+            self.pending.clear();
+        }
+        if size == self.offset {
+            return Vec::new();
+        }
+        let Some(bytes) = self.reader.read_from(self.offset, MAX_LOG_TAIL_READ) else {
+            return Vec::new();
+        };
+        self.offset += bytes.len() as u64;
+        self.behind = self.offset < size;
+
+        // Only the fresh bytes can hold a newline; the held-back fragment
+        // was already scanned.
+        let mut scanned = self.pending.len();
+        self.pending.extend_from_slice(&bytes);
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(pos) = self.pending[scanned..].iter().position(|&b| b == b'\n') {
+            let mut line = &self.pending[start..scanned + pos];
+            if line.last() == Some(&b'\r') {
+                line = &line[..line.len() - 1];
+            }
+            push_pieces(&mut lines, line);
+            start = scanned + pos + 1;
+            scanned = start;
+        }
+        self.pending.drain(..start);
+        while self.pending.len() > MAX_PAYLOAD_SIZE {
+            lines.push(self.pending.drain(..MAX_PAYLOAD_SIZE).collect());
+        }
+        lines
+    }
+}
+
+/// Push `line` onto `lines`, split into pieces of at most [`MAX_PAYLOAD_SIZE`].
+fn push_pieces(lines: &mut Vec<Vec<u8>>, line: &[u8]) {
+    if line.is_empty() {
+        lines.push(Vec::new());
+    } else {
+        lines.extend(line.chunks(MAX_PAYLOAD_SIZE).map(<[u8]>::to_vec));
+    }
+}
+
+#[async_trait]
+impl<R: LogTailReader + 'static> StreamFeed for LogTailFeed<R> {
+    fn feed_id(&self) -> FeedId {
+        self.feed_id
+    }
+
+    fn name(&self) -> &str {
+        "log_tail"
+    }
+
+    async fn run(self: Box<Self>, tx: broadcast::Sender<Frame>, cancel: CancellationToken) {
+        let mut feed = *self;
+        let feed_id = feed.feed_id;
+        let mut ticker = tokio::time::interval(feed.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => loop {
+                    // The reader blocks; hand the feed to a blocking thread
+                    // for the poll and take it back afterwards.
+                    let lines;
+                    (feed, lines) = match tokio::task::spawn_blocking(move || {
+                        let lines = feed.poll();
+                        (feed, lines)
+                    })
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(_) => return,
+                    };
+                    for line in lines {
+                        // No subscribers is not an error for a stream feed.
+                        let _ = tx.send(Frame::new(feed_id, line));
+                    }
+                    if !feed.behind || cancel.is_cancelled() {
+                        break;
+                    }
+                },
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct FakeContents {
+        inode: u64,
+        bytes: Vec<u8>,
+    }
+
+    /// In-memory file whose contents the test mutates between polls.
+    #[derive(Clone, Default)]
+    struct FakeFile(Arc<Mutex<FakeContents>>);
+
+    impl FakeFile {
+        fn append(&self, bytes: &[u8]) {
+            self.0.lock().unwrap().bytes.extend_from_slice(bytes);
+        }
+
+        fn truncate(&self) {
+            self.0.lock().unwrap().bytes.clear();
+        }
+
+        /// Replace the file with a new one holding `bytes`.
+        fn rotate(&self, bytes: &[u8]) {
+            let mut contents = self.0.lock().unwrap();
+            contents.inode += 1;
+            contents.bytes = bytes.to_vec();
+        }
+    }
+
+    impl LogTailReader for FakeFile {
+        fn size(&mut self) -> Option<u64> {
+            Some(self.0.lock().unwrap().bytes.len() as u64)
+        }
+
+        fn identity(&mut self) -> Option<(u64, u64)> {
+            Some((1, self.0.lock().unwrap().inode))
+        }
+
+        fn read_from(&mut self, offset: u64, max: usize) -> Option<Vec<u8>> {
+            let contents = self.0.lock().unwrap();
+            let end = contents.bytes.len().min(offset as usize + max);
+            Some(contents.bytes[offset as usize..end].to_vec())
+        }
+    }
+
+    #[test]
+    fn test_poll_yields_appended_lines_only() {
+        let file = FakeFile::default();
+        file.append(b"old line\n");
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+        assert!(feed.poll().is_empty());
+
+        file.append(b"first\r\nsecond\n");
+        assert_eq!(feed.poll(), vec![b"first".to_vec(), b"second".to_vec()]);
+        assert!(feed.poll().is_empty());
+    }
+
+    #[test]
+    fn test_poll_holds_partial_line_until_completed() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        file.append(b"hel");
+        assert!(feed.poll().is_empty());
+        file.append(b"lo\nwor");
+        assert_eq!(feed.poll(), vec![b"hello".to_vec()]);
+        file.append(b"ld\n");
+        assert_eq!(feed.poll(), vec![b"world".to_vec()]);
+    }
+
+    #[test]
+    fn test_poll_truncation_restarts_from_beginning() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        file.append(b"before rotation\ndangling");
+        assert_eq!(feed.poll(), vec![b"before rotation".to_vec()]);
+
+        file.truncate();
+        file.append(b"fresh\n");
+        assert_eq!(feed.poll(), vec![b"fresh".to_vec()]);
+    }
+
+    #[test]
+    fn test_poll_rotation_to_larger_file_restarts_from_beginning() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        file.append(b"old\ndangling");
+        assert_eq!(feed.poll(), vec![b"old".to_vec()]);
+
+        // The new file is already past the old offset, so only its changed
+        // identity gives the rotation away.
+        file.rotate(b"a much longer first line\nsecond\n");
+        assert_eq!(
+            feed.poll(),
+            vec![b"a much longer first line".to_vec(), b"second".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_poll_splits_line_longer_than_payload_limit() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        let mut long = vec![b'a'; MAX_PAYLOAD_SIZE];
+        long.extend_from_slice(b"tail\nnext\n");
+        file.append(&long);
+
+        let mut lines = Vec::new();
+        loop {
+            lines.extend(feed.poll());
+            if !feed.behind {
+                break;
+            }
+        }
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].len(), MAX_PAYLOAD_SIZE);
+        assert!(lines[0].iter().all(|&b| b == b'a'));
+        assert_eq!(lines[1], b"tail");
+        assert_eq!(lines[2], b"next");
+        assert!(lines.iter().all(|l| l.len() <= MAX_PAYLOAD_SIZE));
+    }
+
+    #[test]
+    fn test_poll_flushes_oversized_fragment_in_limit_sized_pieces() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        file.append(&vec![b'z'; MAX_PAYLOAD_SIZE + 5]);
+        let mut lines = Vec::new();
+        loop {
+            lines.extend(feed.poll());
+            if !feed.behind {
+                break;
+            }
+        }
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), MAX_PAYLOAD_SIZE);
+
+        file.append(b"\n");
+        assert_eq!(feed.poll(), vec![b"zzzzz".to_vec()]);
+    }
+
+    #[test]
+    fn test_poll_caps_each_read() {
+        let file = FakeFile::default();
+        let mut feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone());
+
+        file.append(&vec![b'\n'; MAX_LOG_TAIL_READ + 1]);
+        assert_eq!(feed.poll().len(), MAX_LOG_TAIL_READ);
+        assert!(feed.behind);
+        assert_eq!(feed.poll().len(), 1);
+        assert!(!feed.behind);
+    }
+
+    #[tokio::test]
+    async fn test_run_broadcasts_line_frames() {
+        let file = FakeFile::default();
+        let feed = LogTailFeed::new(FeedId::SHELL_OUTPUT, file.clone())
+            .with_interval(Duration::from_millis(10));
+        assert_eq!(feed.feed_id(), FeedId::SHELL_OUTPUT);
+        assert_eq!(feed.name(), "log_tail");
+
+        let (tx, mut rx) = broadcast::channel(16);
+        let cancel = CancellationToken::new();
+        let handle = tokio::spawn(Box::new(feed).run(tx, cancel.clone()));
+
+        file.append(b"one\ntwo\n");
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(first.feed_id, FeedId::SHELL_OUTPUT);
+        assert_eq!(first.payload, b"one");
+        assert_eq!(second.payload, b"two");
+
+        cancel.cancel();
+        handle.await.unwrap();
+    }
+}
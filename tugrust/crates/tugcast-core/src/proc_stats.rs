@@ -0,0 +1,228 @@
+//! Process resource-stats snapshot feed.
+//!
+//! [`ProcStatsFeed`] samples CPU and memory for one PID on a fixed interval
+//! and publishes each sample as a [`StatSnapshot`] frame. The sampling itself
+//! goes through [`ProcStatsReader`], so the platform probe (sysinfo, `/proc`,
+//! …) stays with the caller and tests can feed scripted readings.
+//!
+//! The payload is a whole [`StatSnapshot`], unlike the per-collector stats
+//! feeds, which carry bare collector JSON. Publish it on a feed id of its
+//! own rather than `STATS_PROCESS_INFO`, whose clients expect the
+//! `process_info` collector's shape. The reading's field names match that
+//! collector (`cpu_percent`, `memory_mb`) so one renderer handles both.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::feed::SnapshotFeed;
+use crate::protocol::{FeedId, Frame};
+use crate::types::StatSnapshot;
+
+/// Default sampling interval for [`ProcStatsFeed`].
+pub const DEFAULT_PROC_STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Collector key the reading is stored under in [`StatSnapshot::collectors`].
+///
+/// Distinct from `process_info`, which always describes tugcast itself;
+/// this collector samples an arbitrary PID.
+pub const PROC_STATS_COLLECTOR: &str = "proc_stats";
+
+/// One CPU/memory sample for a process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcReading {
+    /// CPU usage since the previous sample, in percent of one core
+    pub cpu_percent: f64,
+    /// Resident memory in bytes
+    pub memory_bytes: u64,
+}
+
+/// Source of [`ProcReading`]s for a PID.
+pub trait ProcStatsReader: Send + Sync {
+    /// Sample `pid` now. Returns `None` if the process cannot be read
+    /// (exited, permission denied, …).
+    ///
+    /// May perform blocking I/O; [`ProcStatsFeed`] calls it from
+    /// `spawn_blocking`.
+    fn read(&mut self, pid: u32) -> Option<ProcReading>;
+}
+
+/// Snapshot feed publishing periodic CPU/memory samples for one process.
+pub struct ProcStatsFeed<R> {
+    feed_id: FeedId,
+    pid: u32,
+    reader: R,
+    interval: Duration,
+}
+
+impl<R: ProcStatsReader> ProcStatsFeed<R> {
+    /// Create a feed sampling `pid` through `reader`, publishing on `feed_id`
+    /// every [`DEFAULT_PROC_STATS_INTERVAL`].
+    pub fn new(feed_id: FeedId, pid: u32, reader: R) -> Self {
+        Self {
+            feed_id,
+            pid,
+            reader,
+            interval: DEFAULT_PROC_STATS_INTERVAL,
+        }
+    }
+
+    /// Override the sampling interval.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Take one sample and build its snapshot.
+    ///
+    /// An unreadable process yields a `null` collector value rather than
+    /// skipping the snapshot, matching how the stats collectors report
+    /// failure.
+    pub fn sample(&mut self) -> StatSnapshot {
+        let value = match self.reader.read(self.pid) {
+            Some(reading) => serde_json::json!({
+                "name": PROC_STATS_COLLECTOR,
+                "pid": self.pid,
+                "cpu_percent": reading.cpu_percent,
+                "memory_mb": reading.memory_bytes as f64 / 1_048_576.0,
+            }),
+            None => serde_json::Value::Null,
+        };
+        let mut collectors = HashMap::new();
+        collectors.insert(PROC_STATS_COLLECTOR.to_string(), value);
+        StatSnapshot {
+            collectors,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+#[async_trait]
+impl<R: ProcStatsReader + 'static> SnapshotFeed for ProcStatsFeed<R> {
+    fn feed_id(&self) -> FeedId {
+        self.feed_id
+    }
+
+    fn name(&self) -> &str {
+        "proc_stats"
+    }
+
+    async fn run(self: Box<Self>, tx: watch::Sender<Frame>, cancel: CancellationToken) {
+        let mut feed = *self;
+        let feed_id = feed.feed_id;
+        let mut ticker = tokio::time::interval(feed.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    // The reader may block; hand the feed to a blocking
+                    // thread for the sample and take it back afterwards.
+                    let snapshot;
+                    (feed, snapshot) = match tokio::task::spawn_blocking(move || {
+                        let snapshot = feed.sample();
+                        (feed, snapshot)
+                    })
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(_) => return,
+                    };
+                    let payload = serde_json::to_vec(&snapshot).unwrap_or_default();
+                    if tx.send(Frame::new(feed_id, payload)).is_err() {
+                        return;
+                    }
+                }
+                _ = cancel.cancelled() => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Replays scripted readings, then reports the process as gone.
+    struct FakeReader {
+        readings: VecDeque<ProcReading>,
+        pids: Vec<u32>,
+    }
+
+    impl FakeReader {
+        fn new(readings: &[ProcReading]) -> Self {
+            Self {
+                readings: readings.iter().copied().collect(),
+                pids: Vec::new(),
+            }
+        }
+    }
+
+    impl ProcStatsReader for FakeReader {
+        fn read(&mut self, pid: u32) -> Option<ProcReading> {
+            self.pids.push(pid);
+            self.readings.pop_front()
+        }
+    }
+
+    /// Neutral feed id: `STATS_PROCESS_INFO` carries a different payload.
+    const TEST_FEED: FeedId = FeedId(0x3F);
+
+    const READING: ProcReading = ProcReading {
+        cpu_percent: 12.5,
+        memory_bytes: 64 * 1024 * 1024,
+    };
+
+    #[test]
+    fn test_sample_reports_reading_fields() {
+        let mut feed = ProcStatsFeed::new(TEST_FEED, 4242, FakeReader::new(&[READING]));
+        let snapshot = feed.sample();
+
+        assert_eq!(feed.reader.pids, vec![4242]);
+        let value = &snapshot.collectors[PROC_STATS_COLLECTOR];
+        assert_eq!(value["pid"], 4242);
+        assert_eq!(value["cpu_percent"], 12.5);
+        assert_eq!(value["name"], PROC_STATS_COLLECTOR);
+        assert_eq!(value["memory_mb"], 64.0);
+        assert!(chrono::DateTime::parse_from_rfc3339(&snapshot.timestamp).is_ok());
+    }
+
+    #[test]
+    fn test_sample_unreadable_process_is_null() {
+        let mut feed = ProcStatsFeed::new(TEST_FEED, 1, FakeReader::new(&[]));
+        let snapshot = feed.sample();
+        assert_eq!(
+            snapshot.collectors[PROC_STATS_COLLECTOR],
+            serde_json::Value::Null
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_publishes_stat_snapshot_frames() {
+        let feed = ProcStatsFeed::new(TEST_FEED, 7, FakeReader::new(&[READING]))
+            .with_interval(Duration::from_millis(10));
+        assert_eq!(feed.feed_id(), TEST_FEED);
+        assert_eq!(feed.name(), "proc_stats");
+
+        let (tx, mut rx) = watch::channel(Frame::new(TEST_FEED, vec![]));
+        let cancel = CancellationToken::new();
+        let handle = crate::feed::spawn_snapshot_feed(Box::new(feed), tx, cancel.clone());
+
+        rx.changed().await.unwrap();
+        let frame = rx.borrow_and_update().clone();
+        assert_eq!(frame.feed_id, TEST_FEED);
+        let snapshot: StatSnapshot = serde_json::from_slice(&frame.payload).unwrap();
+        assert_eq!(snapshot.collectors[PROC_STATS_COLLECTOR]["pid"], 7);
+        assert_eq!(
+            snapshot.collectors[PROC_STATS_COLLECTOR]["cpu_percent"],
+            12.5
+        );
+
+        cancel.cancel();
+        handle.await.unwrap();
+    }
+}
@@ -8,8 +8,34 @@
 //! - **FeedId**: open `u8` namespace — known feeds have associated constants,
 //!   unknown values pass through without error (opaque routing).
 //! - **Flags**: bit 0 = frame kind (0 = data, 1 = control/meta).
-//!   Bits 1–7 are reserved and must be 0; receivers ignore unknown flags.
+//!   Bit 1 = partial (one piece of a streamed snapshot), bit 2 = final piece.
+//!   Bit 3 = metadata extension present, bit 4 = timestamp extension present
+//!   (see below). Bit 5 = payload is UTF-8 text (0 = binary).
+//!   Bits 6–7 are reserved and must be 0; receivers ignore unknown flags.
 //! - **Length**: big-endian `u32`, max [`MAX_PAYLOAD_SIZE`].
+//!
+//! ## Header extensions
+//!
+//! An extension flag inserts a region between the fixed header and the
+//! payload; the length field still counts only the payload. Frames without
+//! extension flags are byte-for-byte the v1 layout above. A receiver that
+//! predates an extension cannot skip its region, so extensions are opt-in
+//! (see below): [`Frame::encode_for`] drops any the peer did not declare.
+//!
+//! - **Metadata** (bit 3): `[2 bytes region length (BE u16)][entries]`, each
+//!   entry `[1 byte type][1 byte value length][value]`. See [`Frame::with_meta`].
+//! - **Timestamp** (bit 4): `[8 bytes send time (BE u64)]`, after the metadata
+//!   region when both are present. See [`Frame::with_timestamp`].
+//!
+//! Extension regions appear in flag-bit order.
+//!
+//! ## Opt-in features
+//!
+//! Header extensions change the layout and partial snapshots change how a
+//! receiver must treat payloads, so both are opt-in: a client lists the
+//! features it understands in the `capabilities` array of its handshake
+//! hello, and the server reads them into [`PeerCapabilities`]. A client that
+//! lists nothing gets v1 frames only.
 
 use std::fmt;
 
@@ -258,7 +284,17 @@ impl fmt::Display for TugSessionId {
 /// Flags byte carried in every frame header.
 ///
 /// Bit 0 (`KIND`): `0` = data frame, `1` = control/meta frame about this feed.
-/// Bits 1–7: reserved, must be 0 on send, ignored on receive.
+/// Bit 1 (`PARTIAL`): the payload is one piece of a snapshot streamed across
+/// several frames; see [`SnapshotAssembler`].
+/// Bit 2 (`FINAL`): the last piece of a partial snapshot. Only meaningful
+/// together with `PARTIAL`.
+/// Bit 3 (`META`): a metadata extension region follows the header; see
+/// [`Frame::with_meta`]. Set by [`Frame::encode`] from the frame's entries.
+/// Bit 4 (`TIMESTAMP`): an 8-byte send timestamp follows the header (after
+/// any metadata region); see [`Frame::with_timestamp`].
+/// Bit 5 (`TEXT`): the payload is UTF-8 text (usually JSON); clear means raw
+/// binary. See [`PayloadKind`].
+/// Bits 6–7: reserved, must be 0 on send, ignored on receive.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct FrameFlags(pub u8);
 
@@ -270,6 +306,16 @@ impl FrameFlags {
 
     /// Bit mask for the kind bit.
     const KIND_BIT: u8 = 0x01;
+    /// Bit mask for the partial-snapshot bit.
+    const PARTIAL_BIT: u8 = 0x02;
+    /// Bit mask for the final-piece bit.
+    const FINAL_BIT: u8 = 0x04;
+    /// Bit mask for the metadata-extension bit.
+    const META_BIT: u8 = 0x08;
+    /// Bit mask for the timestamp-extension bit.
+    const TIMESTAMP_BIT: u8 = 0x10;
+    /// Bit mask for the text-payload bit.
+    const TEXT_BIT: u8 = 0x20;
 
     /// Returns `true` if this is a control/meta frame.
     pub fn is_control(self) -> bool {
@@ -280,6 +326,55 @@ impl FrameFlags {
     pub fn is_data(self) -> bool {
         !self.is_control()
     }
+
+    /// Returns `true` if this frame carries one piece of a streamed snapshot.
+    pub fn is_partial(self) -> bool {
+        self.0 & Self::PARTIAL_BIT != 0
+    }
+
+    /// Returns `true` if this is the last piece of a streamed snapshot.
+    pub fn is_final(self) -> bool {
+        self.is_partial() && self.0 & Self::FINAL_BIT != 0
+    }
+
+    /// Returns `true` if a metadata extension region follows the header.
+    pub fn has_meta(self) -> bool {
+        self.0 & Self::META_BIT != 0
+    }
+
+    /// Returns `true` if a send-timestamp extension follows the header.
+    pub fn has_timestamp(self) -> bool {
+        self.0 & Self::TIMESTAMP_BIT != 0
+    }
+
+    /// How the payload should be decoded.
+    pub fn payload_kind(self) -> PayloadKind {
+        if self.0 & Self::TEXT_BIT != 0 {
+            PayloadKind::Text
+        } else {
+            PayloadKind::Binary
+        }
+    }
+
+    /// These flags with the `TEXT` bit set or cleared for `kind`.
+    pub fn with_payload_kind(self, kind: PayloadKind) -> Self {
+        match kind {
+            PayloadKind::Text => Self(self.0 | Self::TEXT_BIT),
+            PayloadKind::Binary => Self(self.0 & !Self::TEXT_BIT),
+        }
+    }
+}
+
+/// Payload encoding advertised by the `TEXT` flag bit.
+///
+/// Frames from senders that predate the bit decode as `Binary`, which is
+/// always safe: a text payload is also valid bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PayloadKind {
+    /// UTF-8 text, usually JSON
+    Text,
+    /// Raw bytes with no encoding guarantee
+    Binary,
 }
 
 impl Default for FrameFlags {
@@ -320,6 +415,52 @@ pub const CLOSE_HANDSHAKE_TIMEOUT: u16 = 4002;
 /// WebSocket close code for malformed handshake.
 pub const CLOSE_BAD_HANDSHAKE: u16 = 4003;
 
+/// Handshake capability: the peer reassembles [`Frame::partial`] pieces.
+pub const CAP_PARTIAL_FRAMES: &str = "partial_frames";
+
+/// Handshake capability: the peer decodes the metadata header extension.
+pub const CAP_FRAME_META: &str = "frame_meta";
+
+/// Handshake capability: the peer decodes the timestamp header extension.
+pub const CAP_FRAME_TIMESTAMP: &str = "frame_timestamp";
+
+/// Opt-in frame features a peer declared in its handshake.
+///
+/// Parsed from the `capabilities` array of the hello; unknown names are
+/// ignored, and a hello without the array declares nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerCapabilities {
+    /// Peer understands partial snapshots ([`CAP_PARTIAL_FRAMES`]). Parsed
+    /// but not yet consulted; see [`Frame::partial`].
+    pub partial_frames: bool,
+    /// Peer decodes the metadata extension ([`CAP_FRAME_META`])
+    pub meta: bool,
+    /// Peer decodes the timestamp extension ([`CAP_FRAME_TIMESTAMP`])
+    pub timestamp: bool,
+}
+
+impl PeerCapabilities {
+    /// Read the capabilities declared in a handshake message.
+    pub fn from_handshake(message: &serde_json::Value) -> Self {
+        let mut caps = Self::default();
+        let names = message
+            .get("capabilities")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str());
+        for name in names {
+            match name {
+                CAP_PARTIAL_FRAMES => caps.partial_frames = true,
+                CAP_FRAME_META => caps.meta = true,
+                CAP_FRAME_TIMESTAMP => caps.timestamp = true,
+                _ => {}
+            }
+        }
+        caps
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ProtocolError
 // ---------------------------------------------------------------------------
@@ -334,6 +475,48 @@ pub enum ProtocolError {
     /// The payload size exceeds the maximum allowed
     #[error("payload too large: {size} bytes exceeds maximum {max}")]
     PayloadTooLarge { size: usize, max: usize },
+
+    /// A metadata extension region is malformed or cannot be encoded
+    #[error("invalid frame metadata: {reason}")]
+    InvalidMeta { reason: String },
+
+    /// The frame decoded fine but reports a feed-level failure — the feed
+    /// itself failed, not the transport. See [`Frame::error`].
+    #[error("feed {feed_id} failed: {code}: {message}")]
+    FeedFailed {
+        feed_id: FeedId,
+        code: String,
+        message: String,
+    },
+}
+
+// ---------------------------------------------------------------------------
+// FeedError
+// ---------------------------------------------------------------------------
+
+/// A feed-level failure carried by an error control frame.
+///
+/// Built by [`Frame::error`] and recovered by [`Frame::feed_error`]. Convert
+/// into [`ProtocolError::FeedFailed`] when the caller wants one error type for
+/// both transport and feed failures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedError {
+    /// The feed that failed
+    pub feed_id: FeedId,
+    /// Short machine-readable reason (e.g. `"session_not_owned"`)
+    pub code: String,
+    /// Human-readable detail; may be empty
+    pub message: String,
+}
+
+impl From<FeedError> for ProtocolError {
+    fn from(err: FeedError) -> Self {
+        ProtocolError::FeedFailed {
+            feed_id: err.feed_id,
+            code: err.code,
+            message: err.message,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -349,6 +532,11 @@ pub struct Frame {
     pub flags: FrameFlags,
     /// The frame payload data
     pub payload: Vec<u8>,
+    /// Metadata extension entries, `(type, value)` in wire order. Private so
+    /// the entries and the `META` flag bit cannot disagree.
+    meta: Vec<(u8, Vec<u8>)>,
+    /// Send timestamp extension. Private for the same reason as `meta`.
+    timestamp: Option<u64>,
 }
 
 impl Frame {
@@ -358,24 +546,117 @@ impl Frame {
             feed_id,
             flags: FrameFlags::DATA,
             payload,
+            meta: Vec::new(),
+            timestamp: None,
         }
     }
 
+    /// Create a data frame carrying UTF-8 text, tagged [`PayloadKind::Text`].
+    pub fn text(feed_id: FeedId, text: &str) -> Self {
+        Self::new(feed_id, text.as_bytes().to_vec()).with_payload_kind(PayloadKind::Text)
+    }
+
+    /// Create a data frame carrying raw bytes, tagged [`PayloadKind::Binary`].
+    pub fn binary(feed_id: FeedId, bytes: &[u8]) -> Self {
+        Self::new(feed_id, bytes.to_vec())
+    }
+
     /// Create a new control/meta frame with the given feed ID and payload.
     pub fn control(feed_id: FeedId, payload: Vec<u8>) -> Self {
         Frame {
             feed_id,
             flags: FrameFlags::CONTROL,
             payload,
+            meta: Vec::new(),
+            timestamp: None,
         }
     }
 
+    /// Create one piece of a snapshot streamed across several data frames.
+    ///
+    /// Pieces of one snapshot are sent in order on the same feed; `last`
+    /// marks the piece that completes it. Receivers concatenate the payloads
+    /// with a [`SnapshotAssembler`].
+    ///
+    /// Only send pieces to a peer whose [`PeerCapabilities::partial_frames`]
+    /// is set; anyone else would take each piece for a whole snapshot. Send
+    /// such peers the whole snapshot as one frame instead.
+    ///
+    /// No router path checks that flag yet: the router forwards frames as
+    /// they come, and no feed sends pieces today. The first feed that does
+    /// must add the check, or the router must reassemble for other peers.
+    pub fn partial(feed_id: FeedId, payload: Vec<u8>, last: bool) -> Self {
+        let mut bits = FrameFlags::PARTIAL_BIT;
+        if last {
+            bits |= FrameFlags::FINAL_BIT;
+        }
+        Frame {
+            feed_id,
+            flags: FrameFlags(bits),
+            payload,
+            meta: Vec::new(),
+            timestamp: None,
+        }
+    }
+
+    /// Returns `true` if this frame is one piece of a streamed snapshot.
+    pub fn is_partial(&self) -> bool {
+        self.flags.is_partial()
+    }
+
+    /// Create an error control frame reporting that `feed_id` failed.
+    ///
+    /// Payload: `{"type":"error","detail":…,"code":…,"message":…}`. The
+    /// router's rejections send `{"type":"error","detail":…}` and tugdeck
+    /// reads `detail`, so the reason goes under `detail` for existing
+    /// clients and is mirrored under `code` for [`Frame::feed_error`].
+    pub fn error(feed_id: FeedId, code: &str, message: &str) -> Self {
+        let payload = serde_json::json!({
+            "type": "error",
+            "detail": code,
+            "code": code,
+            "message": message,
+        });
+        Frame::control(feed_id, serde_json::to_vec(&payload).unwrap_or_default())
+    }
+
+    /// Decode this frame as a feed-level error, if it is one.
+    ///
+    /// Returns `None` for data frames and for control frames whose JSON
+    /// `type` is not `"error"`. The router's `detail`-only shape is accepted
+    /// in place of `code`, and a missing `message` decodes as empty.
+    pub fn feed_error(&self) -> Option<FeedError> {
+        if !self.is_control() {
+            return None;
+        }
+        let value: serde_json::Value = serde_json::from_slice(&self.payload).ok()?;
+        if value.get("type")?.as_str()? != "error" {
+            return None;
+        }
+        let code = value
+            .get("code")
+            .or_else(|| value.get("detail"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let message = value
+            .get("message")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        Some(FeedError {
+            feed_id: self.feed_id,
+            code: code.to_string(),
+            message: message.to_string(),
+        })
+    }
+
     /// Create a heartbeat frame (empty payload).
     pub fn heartbeat() -> Self {
         Frame {
             feed_id: FeedId::HEARTBEAT,
             flags: FrameFlags::DATA,
             payload: Vec::new(),
+            meta: Vec::new(),
+            timestamp: None,
         }
     }
 
@@ -384,29 +665,160 @@ impl Frame {
         self.flags.is_control()
     }
 
+    /// How the payload should be decoded; see [`PayloadKind`].
+    pub fn payload_kind(&self) -> PayloadKind {
+        self.flags.payload_kind()
+    }
+
+    /// Tag the payload as text or binary, e.g. a [`Frame::partial`] piece
+    /// of a JSON snapshot.
+    pub fn with_payload_kind(mut self, kind: PayloadKind) -> Self {
+        self.flags = self.flags.with_payload_kind(kind);
+        self
+    }
+
+    /// Attach out-of-band metadata entries (e.g. a content type) without
+    /// touching the payload.
+    ///
+    /// Entries are `(type, value)` pairs kept in the given order; the type
+    /// byte's meaning is agreed per feed. An empty set leaves the frame
+    /// extension-less. Replaces any metadata already attached.
+    /// Dropped by [`Frame::encode_for`] for peers without [`CAP_FRAME_META`].
+    ///
+    /// # Errors
+    ///
+    /// [`ProtocolError::InvalidMeta`] if a value exceeds 255 bytes or the
+    /// whole region exceeds `u16::MAX` bytes.
+    pub fn with_meta(
+        mut self,
+        entries: impl IntoIterator<Item = (u8, Vec<u8>)>,
+    ) -> Result<Self, ProtocolError> {
+        let entries: Vec<(u8, Vec<u8>)> = entries.into_iter().collect();
+        let mut region = 0usize;
+        for (kind, value) in &entries {
+            if value.len() > u8::MAX as usize {
+                return Err(ProtocolError::InvalidMeta {
+                    reason: format!(
+                        "value for type 0x{kind:02x} is {} bytes (max 255)",
+                        value.len()
+                    ),
+                });
+            }
+            region += 2 + value.len();
+        }
+        if region > u16::MAX as usize {
+            return Err(ProtocolError::InvalidMeta {
+                reason: format!("region is {region} bytes (max {})", u16::MAX),
+            });
+        }
+        if entries.is_empty() {
+            self.flags.0 &= !FrameFlags::META_BIT;
+        } else {
+            self.flags.0 |= FrameFlags::META_BIT;
+        }
+        self.meta = entries;
+        Ok(self)
+    }
+
+    /// Metadata extension entries, in wire order. Empty for extension-less
+    /// frames.
+    pub fn meta(&self) -> &[(u8, Vec<u8>)] {
+        &self.meta
+    }
+
+    /// Stamp the frame with its send time, for end-to-end latency
+    /// measurement.
+    ///
+    /// The value is opaque to the protocol: sender and receiver agree on the
+    /// clock (typically microseconds since the Unix epoch, or a monotonic
+    /// counter when both ends share a host). Replaces any earlier timestamp.
+    /// Dropped by [`Frame::encode_for`] for peers without
+    /// [`CAP_FRAME_TIMESTAMP`].
+    pub fn with_timestamp(mut self, ts: u64) -> Self {
+        self.flags.0 |= FrameFlags::TIMESTAMP_BIT;
+        self.timestamp = Some(ts);
+        self
+    }
+
+    /// Send timestamp, if the frame carries one.
+    pub fn timestamp(&self) -> Option<u64> {
+        self.timestamp
+    }
+
     /// Encode this frame into wire format bytes.
     ///
     /// Wire format (v1):
     /// ```text
-    /// [1 byte feed_id][1 byte flags][4 bytes payload length BE u32][payload]
+    /// [1 byte feed_id][1 byte flags][4 bytes payload length BE u32][extensions][payload]
     /// ```
+    ///
+    /// The `META` and `TIMESTAMP` flag bits are written from the attached
+    /// extensions, whatever `flags` says.
     pub fn encode(&self) -> Vec<u8> {
+        let mut flags = self.flags.0 & !(FrameFlags::META_BIT | FrameFlags::TIMESTAMP_BIT);
+        if !self.meta.is_empty() {
+            flags |= FrameFlags::META_BIT;
+        }
+        if self.timestamp.is_some() {
+            flags |= FrameFlags::TIMESTAMP_BIT;
+        }
         let mut bytes = Vec::with_capacity(HEADER_SIZE + self.payload.len());
         bytes.push(self.feed_id.as_byte());
-        bytes.push(self.flags.0);
+        bytes.push(flags);
         bytes.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        if !self.meta.is_empty() {
+            let region: usize = self.meta.iter().map(|(_, v)| 2 + v.len()).sum();
+            bytes.extend_from_slice(&(region as u16).to_be_bytes());
+            for (kind, value) in &self.meta {
+                bytes.push(*kind);
+                bytes.push(value.len() as u8);
+                bytes.extend_from_slice(value);
+            }
+        }
+        if let Some(ts) = self.timestamp {
+            bytes.extend_from_slice(&ts.to_be_bytes());
+        }
         bytes.extend_from_slice(&self.payload);
         bytes
     }
 
+    /// Encode this frame for a peer, leaving out header extensions it did
+    /// not declare in its handshake.
+    ///
+    /// Extensions carry out-of-band extras, so a peer without them still
+    /// gets a complete v1 frame. Partial pieces are not rewritten here; see
+    /// [`Frame::partial`].
+    pub fn encode_for(&self, peer: PeerCapabilities) -> Vec<u8> {
+        let drop_meta = !self.meta.is_empty() && !peer.meta;
+        let drop_timestamp = self.timestamp.is_some() && !peer.timestamp;
+        if !drop_meta && !drop_timestamp {
+            return self.encode();
+        }
+        let mut stripped = self.clone();
+        if drop_meta {
+            stripped.meta.clear();
+        }
+        if drop_timestamp {
+            stripped.timestamp = None;
+        }
+        stripped.encode()
+    }
+
     /// Decode a frame from wire format bytes.
     ///
     /// Returns `(Frame, bytes_consumed)` on success.
     ///
+    /// The declared length is untrusted input: it is checked against
+    /// [`MAX_PAYLOAD_SIZE`] first and then against the bytes actually
+    /// present, and only then is the payload copied. A crafted header that
+    /// claims a huge length therefore fails without allocating anything
+    /// proportional to the claim.
+    ///
     /// # Errors
     ///
     /// - [`ProtocolError::Incomplete`] if the buffer is too small
     /// - [`ProtocolError::PayloadTooLarge`] if the payload exceeds [`MAX_PAYLOAD_SIZE`]
+    /// - [`ProtocolError::InvalidMeta`] if a metadata entry overruns its region
     pub fn decode(bytes: &[u8]) -> Result<(Frame, usize), ProtocolError> {
         if bytes.len() < HEADER_SIZE {
             return Err(ProtocolError::Incomplete {
@@ -426,7 +838,21 @@ impl Frame {
             });
         }
 
-        let total_size = HEADER_SIZE + length;
+        // Size every extension the flags announce before checking the
+        // buffer, so `needed` names the whole frame. Until the metadata
+        // length prefix has arrived it is a lower bound that the buffer is
+        // already shorter than.
+        let mut total_size = HEADER_SIZE + length;
+        if flags.has_meta() {
+            total_size += 2;
+            if bytes.len() >= HEADER_SIZE + 2 {
+                total_size +=
+                    u16::from_be_bytes([bytes[HEADER_SIZE], bytes[HEADER_SIZE + 1]]) as usize;
+            }
+        }
+        if flags.has_timestamp() {
+            total_size += 8;
+        }
         if bytes.len() < total_size {
             return Err(ProtocolError::Incomplete {
                 needed: total_size,
@@ -434,19 +860,219 @@ impl Frame {
             });
         }
 
-        let payload = bytes[HEADER_SIZE..total_size].to_vec();
+        let mut offset = HEADER_SIZE;
+        let mut meta = Vec::new();
+        if flags.has_meta() {
+            let region = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+            let region_end = offset + 2 + region;
+            meta = decode_meta(&bytes[offset + 2..region_end])?;
+            offset = region_end;
+        }
+
+        let mut timestamp = None;
+        if flags.has_timestamp() {
+            let end = offset + 8;
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(&bytes[offset..end]);
+            timestamp = Some(u64::from_be_bytes(raw));
+            offset = end;
+        }
+
+        let payload = bytes[offset..total_size].to_vec();
 
         Ok((
             Frame {
                 feed_id,
                 flags,
                 payload,
+                meta,
+                timestamp,
             },
             total_size,
         ))
     }
 }
 
+/// Parse a metadata extension region into `(type, value)` entries.
+fn decode_meta(mut region: &[u8]) -> Result<Vec<(u8, Vec<u8>)>, ProtocolError> {
+    let mut entries = Vec::new();
+    while !region.is_empty() {
+        if region.len() < 2 {
+            return Err(ProtocolError::InvalidMeta {
+                reason: "truncated entry header".to_string(),
+            });
+        }
+        let (kind, len) = (region[0], region[1] as usize);
+        if region.len() < 2 + len {
+            return Err(ProtocolError::InvalidMeta {
+                reason: format!("entry 0x{kind:02x} overruns the region"),
+            });
+        }
+        entries.push((kind, region[2..2 + len].to_vec()));
+        region = &region[2 + len..];
+    }
+    Ok(entries)
+}
+
+// ---------------------------------------------------------------------------
+// FrameBuilder
+// ---------------------------------------------------------------------------
+
+/// Incrementally builds a [`Frame`], enforcing [`MAX_PAYLOAD_SIZE`] as the
+/// payload grows.
+///
+/// [`Frame::new`] accepts any payload, so an oversized frame only surfaces
+/// when the peer's [`Frame::decode`] rejects it. Appending through the
+/// builder fails at the append that would cross the limit instead, leaving
+/// the payload built so far intact.
+#[derive(Debug, Clone)]
+pub struct FrameBuilder {
+    feed_id: FeedId,
+    flags: FrameFlags,
+    payload: Vec<u8>,
+}
+
+impl FrameBuilder {
+    /// Start a data frame for `feed_id` with an empty payload.
+    pub fn new(feed_id: FeedId) -> Self {
+        Self {
+            feed_id,
+            flags: FrameFlags::DATA,
+            payload: Vec::new(),
+        }
+    }
+
+    /// Use the given header flags instead of [`FrameFlags::DATA`].
+    pub fn flags(mut self, flags: FrameFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Tag the payload as text or binary. Call after [`FrameBuilder::flags`],
+    /// which replaces the whole flags byte.
+    pub fn payload_kind(mut self, kind: PayloadKind) -> Self {
+        self.flags = self.flags.with_payload_kind(kind);
+        self
+    }
+
+    /// Append `bytes` to the payload.
+    ///
+    /// # Errors
+    ///
+    /// [`ProtocolError::PayloadTooLarge`] if the payload would exceed
+    /// [`MAX_PAYLOAD_SIZE`]; nothing is appended in that case.
+    pub fn append(&mut self, bytes: &[u8]) -> Result<&mut Self, ProtocolError> {
+        let size = self.payload.len() + bytes.len();
+        if size > MAX_PAYLOAD_SIZE {
+            return Err(ProtocolError::PayloadTooLarge {
+                size,
+                max: MAX_PAYLOAD_SIZE,
+            });
+        }
+        self.payload.extend_from_slice(bytes);
+        Ok(self)
+    }
+
+    /// Current payload length in bytes.
+    pub fn len(&self) -> usize {
+        self.payload.len()
+    }
+
+    /// Whether nothing has been appended yet.
+    pub fn is_empty(&self) -> bool {
+        self.payload.is_empty()
+    }
+
+    /// Finish the frame.
+    pub fn build(self) -> Frame {
+        Frame {
+            feed_id: self.feed_id,
+            flags: self.flags,
+            payload: self.payload,
+            meta: Vec::new(),
+            timestamp: None,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SnapshotAssembler
+// ---------------------------------------------------------------------------
+
+/// Reassembles snapshots streamed as [`Frame::partial`] pieces.
+///
+/// Pieces are accumulated per feed until the final piece arrives, at which
+/// point one ordinary data frame carrying the concatenated payload is
+/// released. It keeps the first piece's flags (minus `PARTIAL` and `FINAL`),
+/// metadata and timestamp, so a text-tagged snapshot stays text and the
+/// timestamp marks when streaming began. Frames that are not partial pass
+/// straight through; a whole snapshot arriving mid-stream supersedes (and
+/// discards) the pieces gathered so far for that feed. The assembled payload
+/// is bounded by [`MAX_PAYLOAD_SIZE`], like any single frame.
+#[derive(Debug, Default)]
+pub struct SnapshotAssembler {
+    /// Per feed, the first piece with the payload gathered so far.
+    pending: std::collections::HashMap<FeedId, Frame>,
+}
+
+impl SnapshotAssembler {
+    /// Create an empty assembler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one received frame in.
+    ///
+    /// Returns `Ok(Some(frame))` when a complete frame is available — either
+    /// a pass-through frame or a finished snapshot — and `Ok(None)` while a
+    /// partial snapshot is still accumulating.
+    ///
+    /// # Errors
+    ///
+    /// [`ProtocolError::PayloadTooLarge`] if the accumulated snapshot would
+    /// exceed [`MAX_PAYLOAD_SIZE`]; the partial state for that feed is dropped.
+    pub fn push(&mut self, frame: Frame) -> Result<Option<Frame>, ProtocolError> {
+        if !frame.is_partial() {
+            if frame.flags.is_data() {
+                self.pending.remove(&frame.feed_id);
+            }
+            return Ok(Some(frame));
+        }
+
+        let feed_id = frame.feed_id;
+        let last = frame.flags.is_final();
+        let gathered = self.pending.get(&feed_id).map_or(0, |f| f.payload.len());
+        let size = gathered + frame.payload.len();
+        if size > MAX_PAYLOAD_SIZE {
+            self.pending.remove(&feed_id);
+            return Err(ProtocolError::PayloadTooLarge {
+                size,
+                max: MAX_PAYLOAD_SIZE,
+            });
+        }
+        match self.pending.entry(feed_id) {
+            std::collections::hash_map::Entry::Occupied(mut head) => {
+                head.get_mut().payload.extend_from_slice(&frame.payload);
+            }
+            std::collections::hash_map::Entry::Vacant(slot) => {
+                let mut head = frame;
+                head.flags.0 &= !(FrameFlags::PARTIAL_BIT | FrameFlags::FINAL_BIT);
+                slot.insert(head);
+            }
+        }
+
+        if !last {
+            return Ok(None);
+        }
+        Ok(self.pending.remove(&feed_id))
+    }
+
+    /// Returns `true` if a partial snapshot is in progress for `feed_id`.
+    pub fn is_pending(&self, feed_id: FeedId) -> bool {
+        self.pending.contains_key(&feed_id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -596,6 +1222,16 @@ mod tests {
         assert!(flags.is_control());
     }
 
+    #[test]
+    fn test_flags_partial_and_final() {
+        assert!(!FrameFlags::DATA.is_partial());
+        assert!(FrameFlags(0x02).is_partial());
+        assert!(!FrameFlags(0x02).is_final());
+        assert!(FrameFlags(0x06).is_final());
+        // FINAL without PARTIAL means nothing.
+        assert!(!FrameFlags(0x04).is_final());
+    }
+
     // ---- Frame construction ----
 
     #[test]
@@ -620,6 +1256,501 @@ mod tests {
         assert!(frame.payload.is_empty());
     }
 
+    // ---- Payload kind ----
+
+    #[test]
+    fn test_text_frame_round_trips_as_utf8() {
+        let original = Frame::text(FeedId::STATS, "{\"cpu\":\"½\"}");
+        assert_eq!(original.payload_kind(), PayloadKind::Text);
+
+        let encoded = original.encode();
+        assert_eq!(encoded[1], 0x20);
+        let (decoded, _) = Frame::decode(&encoded).unwrap();
+        assert_eq!(decoded.payload_kind(), PayloadKind::Text);
+        assert_eq!(
+            std::str::from_utf8(&decoded.payload).unwrap(),
+            "{\"cpu\":\"½\"}"
+        );
+    }
+
+    #[test]
+    fn test_binary_frame_preserves_arbitrary_bytes() {
+        let bytes = [0x00, 0xff, 0xc3, 0x28, 0x1b, b'\n'];
+        let original = Frame::binary(FeedId::TERMINAL_OUTPUT, &bytes);
+        assert_eq!(original.payload_kind(), PayloadKind::Binary);
+
+        let encoded = original.encode();
+        assert_eq!(encoded[1], 0x00);
+        let (decoded, _) = Frame::decode(&encoded).unwrap();
+        assert_eq!(decoded.payload_kind(), PayloadKind::Binary);
+        assert_eq!(decoded.payload, bytes);
+    }
+
+    #[test]
+    fn test_untagged_frames_are_binary() {
+        assert_eq!(
+            Frame::new(FeedId::STATS, b"{}".to_vec()).payload_kind(),
+            PayloadKind::Binary
+        );
+        assert_eq!(FrameFlags(0x21).payload_kind(), PayloadKind::Text);
+        assert!(FrameFlags(0x21).is_control());
+    }
+
+    // ---- Timestamp extension ----
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let original = Frame::new(FeedId::TERMINAL_OUTPUT, b"hi".to_vec())
+            .with_timestamp(1_760_000_000_123_456);
+        assert!(original.flags.has_timestamp());
+
+        let encoded = original.encode();
+        assert_eq!(encoded.len(), HEADER_SIZE + 8 + 2);
+        assert_eq!(encoded[1], 0x10);
+        assert_eq!(&encoded[6..14], &1_760_000_000_123_456u64.to_be_bytes());
+        let (decoded, consumed) = Frame::decode(&encoded).unwrap();
+        assert_eq!(consumed, encoded.len());
+        assert_eq!(decoded, original);
+        assert_eq!(decoded.timestamp(), Some(1_760_000_000_123_456));
+        assert_eq!(decoded.payload, b"hi");
+    }
+
+    #[test]
+    fn test_timestamp_absent_keeps_v1_layout() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec());
+        assert_eq!(frame.timestamp(), None);
+        assert_eq!(
+            frame.encode(),
+            vec![0x30, 0x00, 0x00, 0x00, 0x00, 0x02, 0x7b, 0x7d]
+        );
+        let (decoded, _) = Frame::decode(&frame.encode()).unwrap();
+        assert_eq!(decoded.timestamp(), None);
+        assert!(!decoded.flags.has_timestamp());
+    }
+
+    #[test]
+    fn test_timestamp_follows_meta_region() {
+        let original = Frame::control(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap()
+            .with_timestamp(7);
+        let encoded = original.encode();
+        assert_eq!(
+            encoded,
+            vec![
+                0x30, 0x19, 0x00, 0x00, 0x00, 0x02, // header, CONTROL|META|TIMESTAMP
+                0x00, 0x04, 0x01, 0x02, b'j', b's', // metadata region
+                0, 0, 0, 0, 0, 0, 0, 7, // timestamp
+                0x7b, 0x7d, // payload
+            ]
+        );
+        let (decoded, _) = Frame::decode(&encoded).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn test_timestamp_stripped_for_peer_without_capability() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap()
+            .with_timestamp(7);
+        let meta_only = PeerCapabilities {
+            meta: true,
+            ..PeerCapabilities::default()
+        };
+        let (decoded, _) = Frame::decode(&frame.encode_for(meta_only)).unwrap();
+        assert_eq!(decoded.timestamp(), None);
+        assert_eq!(decoded.meta(), frame.meta());
+
+        let both = PeerCapabilities {
+            meta: true,
+            timestamp: true,
+            ..PeerCapabilities::default()
+        };
+        assert_eq!(frame.encode_for(both), frame.encode());
+    }
+
+    #[test]
+    fn test_timestamp_truncated_is_incomplete() {
+        let encoded = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_timestamp(42)
+            .encode();
+        assert_eq!(
+            Frame::decode(&encoded[..10]),
+            Err(ProtocolError::Incomplete {
+                needed: encoded.len(),
+                have: 10
+            })
+        );
+    }
+
+    #[test]
+    fn test_meta_and_timestamp_truncated_needs_whole_frame() {
+        let encoded = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap()
+            .with_timestamp(42)
+            .encode();
+        // Cut inside the metadata region: `needed` must still count the
+        // 8 timestamp bytes that follow it.
+        assert_eq!(
+            Frame::decode(&encoded[..9]),
+            Err(ProtocolError::Incomplete {
+                needed: encoded.len(),
+                have: 9
+            })
+        );
+        assert_eq!(
+            Frame::decode(&encoded[..7]),
+            Err(ProtocolError::Incomplete {
+                needed: HEADER_SIZE + 2 + 8 + 2,
+                have: 7
+            })
+        );
+    }
+
+    // ---- Metadata extension ----
+
+    #[test]
+    fn test_meta_round_trip_two_entries() {
+        let original = Frame::new(FeedId::FILETREE, b"{\"files\":[]}".to_vec())
+            .with_meta([
+                (0x01, b"application/json".to_vec()),
+                (0x02, b"gzip".to_vec()),
+            ])
+            .unwrap();
+        assert!(original.flags.has_meta());
+
+        let encoded = original.encode();
+        assert_eq!(encoded[1], 0x08);
+        let (decoded, consumed) = Frame::decode(&encoded).unwrap();
+        assert_eq!(consumed, encoded.len());
+        assert_eq!(decoded, original);
+        assert_eq!(
+            decoded.meta(),
+            &[
+                (0x01, b"application/json".to_vec()),
+                (0x02, b"gzip".to_vec())
+            ]
+        );
+        assert_eq!(decoded.payload, b"{\"files\":[]}");
+    }
+
+    #[test]
+    fn test_meta_absent_keeps_v1_layout() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec());
+        assert!(frame.meta().is_empty());
+        assert_eq!(
+            frame.encode(),
+            vec![0x30, 0x00, 0x00, 0x00, 0x00, 0x02, 0x7b, 0x7d]
+        );
+        let (decoded, _) = Frame::decode(&frame.encode()).unwrap();
+        assert!(decoded.meta().is_empty());
+        assert!(!decoded.flags.has_meta());
+
+        // Clearing metadata drops the flag again.
+        let cleared = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"x".to_vec())])
+            .unwrap()
+            .with_meta([])
+            .unwrap();
+        assert_eq!(cleared.encode(), frame.encode());
+    }
+
+    #[test]
+    fn test_meta_golden_bytes() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap();
+        assert_eq!(
+            frame.encode(),
+            vec![
+                0x30, 0x08, 0x00, 0x00, 0x00, 0x02, // header, META flag, length 2
+                0x00, 0x04, // region length 4
+                0x01, 0x02, b'j', b's', // entry: type 1, length 2, "js"
+                0x7b, 0x7d, // payload
+            ]
+        );
+    }
+
+    #[test]
+    fn test_meta_stripped_for_peer_without_capability() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap();
+        let legacy = PeerCapabilities::default();
+        assert_eq!(
+            frame.encode_for(legacy),
+            vec![0x30, 0x00, 0x00, 0x00, 0x00, 0x02, 0x7b, 0x7d]
+        );
+        let aware = PeerCapabilities {
+            meta: true,
+            ..PeerCapabilities::default()
+        };
+        assert_eq!(frame.encode_for(aware), frame.encode());
+    }
+
+    #[test]
+    fn test_meta_value_too_long_rejected() {
+        let result = Frame::new(FeedId::STATS, vec![]).with_meta([(0x01, vec![0u8; 256])]);
+        assert!(matches!(result, Err(ProtocolError::InvalidMeta { .. })));
+    }
+
+    #[test]
+    fn test_meta_overrunning_entry_rejected() {
+        // Region of 3 bytes holding an entry that claims 5 value bytes.
+        let bytes = vec![0x30, 0x08, 0, 0, 0, 0, 0x00, 0x03, 0x01, 0x05, b'x'];
+        assert!(matches!(
+            Frame::decode(&bytes),
+            Err(ProtocolError::InvalidMeta { .. })
+        ));
+    }
+
+    #[test]
+    fn test_meta_truncated_region_is_incomplete() {
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec())
+            .with_meta([(0x01, b"js".to_vec())])
+            .unwrap();
+        let encoded = frame.encode();
+        assert_eq!(
+            Frame::decode(&encoded[..9]),
+            Err(ProtocolError::Incomplete {
+                needed: encoded.len(),
+                have: 9
+            })
+        );
+        assert_eq!(
+            Frame::decode(&encoded[..7]),
+            Err(ProtocolError::Incomplete {
+                needed: HEADER_SIZE + 2 + 2,
+                have: 7
+            })
+        );
+    }
+
+    // ---- FrameBuilder ----
+
+    #[test]
+    fn test_frame_builder_builds_valid_frame() {
+        let mut builder = FrameBuilder::new(FeedId::CODE_OUTPUT);
+        assert!(builder.is_empty());
+        builder
+            .append(b"{\"type\":")
+            .unwrap()
+            .append(b"\"x\"}")
+            .unwrap();
+        assert_eq!(builder.len(), 12);
+        let frame = builder.build();
+        assert_eq!(
+            frame,
+            Frame::new(FeedId::CODE_OUTPUT, br#"{"type":"x"}"#.to_vec())
+        );
+        let (decoded, _) = Frame::decode(&frame.encode()).unwrap();
+        assert_eq!(decoded, frame);
+    }
+
+    #[test]
+    fn test_frame_builder_flags() {
+        let frame = FrameBuilder::new(FeedId::CODE_OUTPUT)
+            .flags(FrameFlags::CONTROL)
+            .build();
+        assert!(frame.is_control());
+        assert!(frame.payload.is_empty());
+    }
+
+    #[test]
+    fn test_frame_builder_payload_kind() {
+        let frame = FrameBuilder::new(FeedId::STATS)
+            .flags(FrameFlags::CONTROL)
+            .payload_kind(PayloadKind::Text)
+            .build();
+        assert!(frame.is_control());
+        assert_eq!(frame.payload_kind(), PayloadKind::Text);
+    }
+
+    #[test]
+    fn test_frame_builder_rejects_append_past_limit() {
+        let mut builder = FrameBuilder::new(FeedId::FILESYSTEM);
+        builder.append(&vec![0u8; MAX_PAYLOAD_SIZE - 1]).unwrap();
+        let err = builder.append(b"ab").unwrap_err();
+        assert_eq!(
+            err,
+            ProtocolError::PayloadTooLarge {
+                size: MAX_PAYLOAD_SIZE + 1,
+                max: MAX_PAYLOAD_SIZE
+            }
+        );
+        // The failed append left the payload untouched; filling exactly to
+        // the limit still succeeds.
+        assert_eq!(builder.len(), MAX_PAYLOAD_SIZE - 1);
+        builder.append(b"a").unwrap();
+        assert_eq!(builder.build().payload.len(), MAX_PAYLOAD_SIZE);
+    }
+
+    // ---- Partial snapshots ----
+
+    #[test]
+    fn test_partial_snapshot_three_pieces_reassemble() {
+        let snapshot = br#"{"branch":"main","files":["a.rs","b.rs","c.rs"]}"#;
+        let pieces: Vec<&[u8]> = vec![&snapshot[..10], &snapshot[10..30], &snapshot[30..]];
+        let mut assembler = SnapshotAssembler::new();
+
+        let mut out = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            let frame = Frame::partial(FeedId::FILESYSTEM, piece.to_vec(), i == 2);
+            // Pieces survive the wire like any other frame.
+            let (decoded, _) = Frame::decode(&frame.encode()).unwrap();
+            assert!(decoded.is_partial());
+            out.push(assembler.push(decoded).unwrap());
+        }
+
+        assert_eq!(out[0], None);
+        assert_eq!(out[1], None);
+        let whole = out[2].clone().expect("final piece completes the snapshot");
+        assert_eq!(whole, Frame::new(FeedId::FILESYSTEM, snapshot.to_vec()));
+        assert!(!assembler.is_pending(FeedId::FILESYSTEM));
+    }
+
+    #[test]
+    fn test_assembler_keeps_text_tag_meta_and_timestamp() {
+        let mut assembler = SnapshotAssembler::new();
+        let first = Frame::partial(FeedId::FILESYSTEM, b"{\"a\":".to_vec(), false)
+            .with_payload_kind(PayloadKind::Text)
+            .with_meta([(0x01, b"json".to_vec())])
+            .unwrap()
+            .with_timestamp(99);
+        let last = Frame::partial(FeedId::FILESYSTEM, b"1}".to_vec(), true)
+            .with_payload_kind(PayloadKind::Text);
+        assert_eq!(assembler.push(first).unwrap(), None);
+        let whole = assembler.push(last).unwrap().unwrap();
+
+        assert_eq!(whole.payload, b"{\"a\":1}");
+        assert_eq!(whole.payload_kind(), PayloadKind::Text);
+        assert!(!whole.is_partial());
+        assert!(!whole.flags.is_final());
+        assert_eq!(whole.meta(), &[(0x01, b"json".to_vec())]);
+        assert_eq!(whole.timestamp(), Some(99));
+    }
+
+    #[test]
+    fn test_assembler_passes_whole_frames_through() {
+        let mut assembler = SnapshotAssembler::new();
+        let frame = Frame::new(FeedId::STATS, b"{}".to_vec());
+        assert_eq!(assembler.push(frame.clone()).unwrap(), Some(frame));
+    }
+
+    #[test]
+    fn test_assembler_whole_snapshot_supersedes_pending_pieces() {
+        let mut assembler = SnapshotAssembler::new();
+        let first = Frame::partial(FeedId::FILETREE, b"stale".to_vec(), false);
+        assert_eq!(assembler.push(first).unwrap(), None);
+        assert!(assembler.is_pending(FeedId::FILETREE));
+
+        // A control frame on the same feed does not disturb the stream...
+        let ctl = Frame::control(FeedId::FILETREE, b"{}".to_vec());
+        assert!(assembler.push(ctl).unwrap().is_some());
+        assert!(assembler.is_pending(FeedId::FILETREE));
+
+        // ...but a whole data snapshot replaces it.
+        let whole = Frame::new(FeedId::FILETREE, b"fresh".to_vec());
+        assert_eq!(assembler.push(whole.clone()).unwrap(), Some(whole));
+        assert!(!assembler.is_pending(FeedId::FILETREE));
+    }
+
+    #[test]
+    fn test_assembler_keeps_feeds_independent() {
+        let mut assembler = SnapshotAssembler::new();
+        assembler
+            .push(Frame::partial(FeedId::FILESYSTEM, b"ab".to_vec(), false))
+            .unwrap();
+        assembler
+            .push(Frame::partial(FeedId::FILETREE, b"xy".to_vec(), false))
+            .unwrap();
+        let fs = assembler
+            .push(Frame::partial(FeedId::FILESYSTEM, b"c".to_vec(), true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(fs.payload, b"abc");
+        assert!(assembler.is_pending(FeedId::FILETREE));
+    }
+
+    #[test]
+    fn test_assembler_rejects_oversized_snapshot() {
+        let mut assembler = SnapshotAssembler::new();
+        let half = vec![0u8; MAX_PAYLOAD_SIZE / 2 + 1];
+        assembler
+            .push(Frame::partial(FeedId::FILESYSTEM, half.clone(), false))
+            .unwrap();
+        let result = assembler.push(Frame::partial(FeedId::FILESYSTEM, half, true));
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadTooLarge {
+                size: 2 * (MAX_PAYLOAD_SIZE / 2 + 1),
+                max: MAX_PAYLOAD_SIZE
+            })
+        );
+        assert!(!assembler.is_pending(FeedId::FILESYSTEM));
+    }
+
+    // ---- Error frames ----
+
+    #[test]
+    fn test_error_frame_round_trip() {
+        let original = Frame::error(FeedId::FILETREE, "scan_failed", "permission denied: /root");
+        assert!(original.is_control());
+        let encoded = original.encode();
+        let (decoded, _) = Frame::decode(&encoded).unwrap();
+        let err = decoded.feed_error().expect("error frame");
+        assert_eq!(err.feed_id, FeedId::FILETREE);
+        assert_eq!(err.code, "scan_failed");
+        assert_eq!(err.message, "permission denied: /root");
+
+        // Existing clients read the reason from `detail`.
+        let value: serde_json::Value = serde_json::from_slice(&decoded.payload).unwrap();
+        assert_eq!(value["detail"], "scan_failed");
+    }
+
+    #[test]
+    fn test_feed_error_maps_to_protocol_error() {
+        let err = Frame::error(FeedId::CODE_OUTPUT, "bridge_exited", "exit 1")
+            .feed_error()
+            .unwrap();
+        let protocol: ProtocolError = err.into();
+        assert_eq!(
+            protocol,
+            ProtocolError::FeedFailed {
+                feed_id: FeedId::CODE_OUTPUT,
+                code: "bridge_exited".to_string(),
+                message: "exit 1".to_string(),
+            }
+        );
+        assert_eq!(
+            protocol.to_string(),
+            "feed CodeOutput(0x40) failed: bridge_exited: exit 1"
+        );
+    }
+
+    #[test]
+    fn test_feed_error_accepts_legacy_detail_key() {
+        let frame = Frame::control(
+            FeedId::CODE_INPUT,
+            br#"{"type":"error","detail":"session_not_owned"}"#.to_vec(),
+        );
+        let err = frame.feed_error().unwrap();
+        assert_eq!(err.code, "session_not_owned");
+        assert_eq!(err.message, "");
+    }
+
+    #[test]
+    fn test_feed_error_none_for_non_error_frames() {
+        let data = Frame::new(FeedId::CODE_OUTPUT, br#"{"type":"error"}"#.to_vec());
+        assert!(data.feed_error().is_none());
+        let lag = Frame::control(FeedId::CODE_OUTPUT, br#"{"type":"lag_recovery"}"#.to_vec());
+        assert!(lag.feed_error().is_none());
+        let opaque = Frame::control(FeedId(0x99), vec![0xFF, 0x00]);
+        assert!(opaque.feed_error().is_none());
+    }
+
     // ---- Encode/decode round trips ----
 
     #[test]
@@ -940,6 +2071,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decode_max_length_header_is_rejected_before_buffer_check() {
+        // A header claiming u32::MAX with no payload behind it must report
+        // the size violation, not wait for (or allocate) 4 GiB of input.
+        let bytes = vec![0x40, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, b'x'];
+        let result = Frame::decode(&bytes);
+        assert_eq!(
+            result,
+            Err(ProtocolError::PayloadTooLarge {
+                size: u32::MAX as usize,
+                max: MAX_PAYLOAD_SIZE
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_length_beyond_buffer_within_max() {
+        // Claiming exactly MAX_PAYLOAD_SIZE is legal, but the buffer only
+        // carries 3 payload bytes — Incomplete, with no payload copied.
+        let claimed = MAX_PAYLOAD_SIZE as u32;
+        let mut bytes = vec![0x00, 0x00];
+        bytes.extend_from_slice(&claimed.to_be_bytes());
+        bytes.extend_from_slice(b"abc");
+        let result = Frame::decode(&bytes);
+        assert_eq!(
+            result,
+            Err(ProtocolError::Incomplete {
+                needed: HEADER_SIZE + MAX_PAYLOAD_SIZE,
+                have: HEADER_SIZE + 3
+            })
+        );
+    }
+
     #[test]
     fn test_decode_with_extra_bytes() {
         let frame = Frame::new(FeedId::TERMINAL_OUTPUT, b"test".to_vec());
@@ -1000,6 +2164,26 @@ mod tests {
         assert_eq!(hello["version"], 1);
     }
 
+    #[test]
+    fn test_peer_capabilities_from_handshake() {
+        let hello = serde_json::json!({
+            "protocol": PROTOCOL_NAME,
+            "version": PROTOCOL_VERSION,
+            "capabilities": [CAP_PARTIAL_FRAMES, CAP_FRAME_META, "some_future_feature", 7],
+        });
+        let caps = PeerCapabilities::from_handshake(&hello);
+        assert!(caps.partial_frames);
+        assert!(caps.meta);
+        assert!(!caps.timestamp);
+
+        // A v1 hello without the array opts in to nothing.
+        let legacy = serde_json::json!({ "protocol": PROTOCOL_NAME, "version": 1 });
+        assert_eq!(
+            PeerCapabilities::from_handshake(&legacy),
+            PeerCapabilities::default()
+        );
+    }
+
     #[test]
     fn test_handshake_response_json() {
         // Verify the server response format
@@ -31,7 +31,7 @@ use tracing::{debug, error, info, warn};
 
 use tugcast_core::{
     CLOSE_BAD_HANDSHAKE, CLOSE_HANDSHAKE_TIMEOUT, CLOSE_VERSION_MISMATCH, FeedId, Frame,
-    HANDSHAKE_TIMEOUT, PROTOCOL_NAME, PROTOCOL_VERSION, TugSessionId,
+    HANDSHAKE_TIMEOUT, PROTOCOL_NAME, PROTOCOL_VERSION, PeerCapabilities, TugSessionId,
 };
 
 use crate::auth::{self, SharedAuthState};
@@ -319,7 +319,10 @@ fn build_handshake_response() -> serde_json::Value {
 }
 
 /// Perform the protocol handshake at WebSocket connection open.
-async fn perform_handshake(socket: &mut WebSocket) -> bool {
+///
+/// Returns the opt-in frame features the client declared, or `None` if the
+/// handshake failed and the connection should be dropped.
+async fn perform_handshake(socket: &mut WebSocket) -> Option<PeerCapabilities> {
     let hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, socket.recv()).await;
 
     let hello_text = match hello {
@@ -332,15 +335,15 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
                     reason: "expected text handshake frame".into(),
                 })))
                 .await;
-            return false;
+            return None;
         }
         Ok(Some(Err(e))) => {
             warn!("Handshake failed: WebSocket error: {}", e);
-            return false;
+            return None;
         }
         Ok(None) => {
             info!("Client disconnected before handshake");
-            return false;
+            return None;
         }
         Err(_) => {
             warn!("Handshake timed out");
@@ -350,7 +353,7 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
                     reason: "handshake timeout".into(),
                 })))
                 .await;
-            return false;
+            return None;
         }
     };
 
@@ -364,7 +367,7 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
                     reason: "invalid handshake JSON".into(),
                 })))
                 .await;
-            return false;
+            return None;
         }
     };
 
@@ -380,7 +383,7 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
                 reason: format!("unknown protocol: {protocol}").into(),
             })))
             .await;
-        return false;
+        return None;
     }
 
     let version = hello_json
@@ -402,7 +405,7 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
                 .into(),
             })))
             .await;
-        return false;
+        return None;
     }
 
     let response = build_handshake_response();
@@ -412,11 +415,11 @@ async fn perform_handshake(socket: &mut WebSocket) -> bool {
         .is_err()
     {
         info!("Client disconnected during handshake response");
-        return false;
+        return None;
     }
 
     info!("Protocol handshake complete (v{})", PROTOCOL_VERSION);
-    true
+    Some(PeerCapabilities::from_handshake(&hello_json))
 }
 
 // ---------------------------------------------------------------------------
@@ -661,9 +664,9 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
     info!(client_id, "Client connected");
 
     // --- Protocol handshake (v1) ---
-    if !perform_handshake(&mut socket).await {
+    let Some(peer) = perform_handshake(&mut socket).await else {
         return;
-    }
+    };
 
     // Build the StreamMap for output fan-in
     let (mut stream_map, lag_policies) = build_stream_map(&router.stream_outputs);
@@ -694,7 +697,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                         Ok(snapshot) => {
                             let frame = Frame::new(FeedId::TERMINAL_OUTPUT, snapshot);
                             if socket
-                                .send(Message::Binary(frame.encode().into()))
+                                .send(Message::Binary(frame.encode_for(peer).into()))
                                 .await
                                 .is_err()
                             {
@@ -734,7 +737,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                 // Flush buffer to client
                 for frame in buffer.drain(..) {
                     if socket
-                        .send(Message::Binary(frame.encode().into()))
+                        .send(Message::Binary(frame.encode_for(peer).into()))
                         .await
                         .is_err()
                     {
@@ -759,7 +762,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                     let frame = watch_rx.borrow_and_update().clone();
                     if !frame.payload.is_empty()
                         && socket
-                            .send(Message::Binary(frame.encode().into()))
+                            .send(Message::Binary(frame.encode_for(peer).into()))
                             .await
                             .is_err()
                     {
@@ -817,7 +820,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                 loop {
                     tokio::select! {
                         Some(frame) = snap_rx.recv() => {
-                            if socket.send(Message::Binary(frame.encode().into())).await.is_err() {
+                            if socket.send(Message::Binary(frame.encode_for(peer).into())).await.is_err() {
                                 info!(client_id, "Client disconnected");
                                 teardown_client(&router, client_id).await;
                                 return;
@@ -827,7 +830,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                         Some((feed_id, result)) = stream_map.next() => {
                             match result {
                                 Ok(frame) => {
-                                    if socket.send(Message::Binary(frame.encode().into())).await.is_err() {
+                                    if socket.send(Message::Binary(frame.encode_for(peer).into())).await.is_err() {
                                         info!(client_id, "Client disconnected");
                                         teardown_client(&router, client_id).await;
                                         return;
@@ -874,7 +877,7 @@ async fn handle_client(mut socket: WebSocket, mut router: FeedRouter) {
                                             }
                                             // Replay buffered frames
                                             for frame in replay_buf.snapshot() {
-                                                if socket.send(Message::Binary(frame.encode().into())).await.is_err() {
+                                                if socket.send(Message::Binary(frame.encode_for(peer).into())).await.is_err() {
                                                     info!(client_id, "Client disconnected during replay");
                                                     teardown_client(&router, client_id).await;
                                                     return;